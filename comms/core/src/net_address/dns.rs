@@ -0,0 +1,279 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::{
+    fmt,
+    net::{IpAddr, ToSocketAddrs},
+};
+
+use multiaddr::{Multiaddr, Protocol};
+
+/// Errors that can occur while parsing or resolving an address given to [`super::MultiaddressesWithStats::from_urls_with_source`]
+/// or [`super::MultiaddressesWithStats::add_resolved`].
+#[derive(Debug)]
+pub enum NetAddressError {
+    /// `input` could not be parsed as a multiaddr, URL, or `host:port` string
+    InvalidAddress(String),
+    /// DNS resolution of `host` failed with the wrapped error
+    DnsResolutionFailed(String, String),
+    /// Resolving `host` returned no addresses at all
+    NoAddressesResolved(String),
+}
+
+impl fmt::Display for NetAddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetAddressError::InvalidAddress(input) => write!(f, "'{}' is not a valid multiaddr, URL or host:port", input),
+            NetAddressError::DnsResolutionFailed(host, err) => write!(f, "failed to resolve host '{}': {}", host, err),
+            NetAddressError::NoAddressesResolved(host) => write!(f, "resolving host '{}' returned no addresses", host),
+        }
+    }
+}
+
+impl std::error::Error for NetAddressError {}
+
+/// Resolves a DNS hostname to a set of IP addresses. Abstracted behind a trait so that address resolution can be
+/// swapped out or mocked in tests instead of hitting the operating system's resolver.
+pub trait DnsResolver {
+    fn resolve(&self, host: &str) -> Result<Vec<IpAddr>, NetAddressError>;
+}
+
+/// A [`DnsResolver`] that defers to the operating system's resolver.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemDnsResolver;
+
+impl DnsResolver for SystemDnsResolver {
+    fn resolve(&self, host: &str) -> Result<Vec<IpAddr>, NetAddressError> {
+        // ToSocketAddrs requires a port to do the lookup; 0 is a placeholder that is discarded below.
+        (host, 0u16)
+            .to_socket_addrs()
+            .map(|addrs| addrs.map(|addr| addr.ip()).collect())
+            .map_err(|err| NetAddressError::DnsResolutionFailed(host.to_string(), err.to_string()))
+    }
+}
+
+/// Collapses an IPv4-mapped IPv6 address to its IPv4 representation, so that the same endpoint written as an IPv6
+/// or IPv4 address is never stored as two distinct entries with split stats.
+fn canonicalize_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(IpAddr::V6(v6)),
+        ip @ IpAddr::V4(_) => ip,
+    }
+}
+
+/// Re-emits `addr` with any IPv4-mapped IPv6 host component collapsed to its IPv4 representation. Every other
+/// component, including Tor/I2P addresses, is passed through unchanged.
+fn canonicalize_multiaddr(addr: &Multiaddr) -> Multiaddr {
+    let mut canonical = Multiaddr::empty();
+    for protocol in addr.iter() {
+        match protocol {
+            Protocol::Ip6(v6) => match v6.to_ipv4_mapped() {
+                Some(v4) => canonical.push(Protocol::Ip4(v4)),
+                None => canonical.push(Protocol::Ip6(v6)),
+            },
+            other => canonical.push(other),
+        }
+    }
+    canonical
+}
+
+/// Finds the position and hostname of the `/dns4`, `/dns6` or `/dnsaddr` component of `addr`. Returns `None` if
+/// `addr` does not contain a DNS host component (e.g. it is already a concrete IP, Tor or I2P address).
+fn dns_host_position(addr: &Multiaddr) -> Option<(usize, String)> {
+    addr.iter().enumerate().find_map(|(i, protocol)| match protocol {
+        Protocol::Dns(h) | Protocol::Dns4(h) | Protocol::Dns6(h) | Protocol::Dnsaddr(h) => Some((i, h.to_string())),
+        _ => None,
+    })
+}
+
+/// Rebuilds `addr` with the component at `dns_index` (its DNS host) replaced by `ip`, leaving every other
+/// component - transport, port, `/p2p/<id>`, and so on - untouched.
+fn replace_dns_component(addr: &Multiaddr, dns_index: usize, ip: IpAddr) -> Multiaddr {
+    let mut resolved = Multiaddr::empty();
+    for (i, protocol) in addr.iter().enumerate() {
+        if i == dns_index {
+            match canonicalize_ip(ip) {
+                IpAddr::V4(v4) => resolved.push(Protocol::Ip4(v4)),
+                IpAddr::V6(v6) => resolved.push(Protocol::Ip6(v6)),
+            }
+        } else {
+            resolved.push(protocol);
+        }
+    }
+    resolved
+}
+
+/// Parses a plain `host:port` string or a URL such as `tcp://example.com:18189`, stripping any scheme prefix.
+/// Handles bracketed (`[::1]:8000`) and bare (`::1`) IPv6 literals so that a host containing colons is never
+/// misread as a `host:port` split. Returns `None` if `input` is empty, or if a bracketed IPv6 literal has no closing
+/// `]` or is followed by anything other than `:<port>`.
+fn parse_host_port_str(input: &str) -> Option<(String, Option<u16>)> {
+    let without_scheme = input.rsplit_once("://").map(|(_, rest)| rest).unwrap_or(input).trim();
+    if without_scheme.is_empty() {
+        return None;
+    }
+
+    if let Some(rest) = without_scheme.strip_prefix('[') {
+        let (host, after) = rest.split_once(']')?;
+        return match after {
+            "" => Some((host.to_string(), None)),
+            _ => after.strip_prefix(':')?.parse::<u16>().ok().map(|port| (host.to_string(), Some(port))),
+        };
+    }
+
+    // A bare (unbracketed) IPv6 literal has more than one colon; treat the whole thing as the host, not a
+    // `host:port` split, since it has no unambiguous port delimiter.
+    if without_scheme.parse::<std::net::Ipv6Addr>().is_ok() {
+        return Some((without_scheme.to_string(), None));
+    }
+
+    match without_scheme.rsplit_once(':') {
+        Some((host, port_str)) => match port_str.parse::<u16>() {
+            Ok(port) => Some((host.to_string(), Some(port))),
+            Err(_) => Some((without_scheme.to_string(), None)),
+        },
+        None => Some((without_scheme.to_string(), None)),
+    }
+}
+
+fn build_multiaddr(ip: IpAddr, port: Option<u16>) -> Multiaddr {
+    let mut multiaddr = Multiaddr::empty();
+    match canonicalize_ip(ip) {
+        IpAddr::V4(v4) => multiaddr.push(Protocol::Ip4(v4)),
+        IpAddr::V6(v6) => multiaddr.push(Protocol::Ip6(v6)),
+    }
+    if let Some(port) = port {
+        multiaddr.push(Protocol::Tcp(port));
+    }
+    multiaddr
+}
+
+fn resolve_host(host: &str, port: Option<u16>, resolver: &dyn DnsResolver) -> Result<Vec<Multiaddr>, NetAddressError> {
+    let ips = resolver.resolve(host)?;
+    if ips.is_empty() {
+        return Err(NetAddressError::NoAddressesResolved(host.to_string()));
+    }
+    Ok(ips.into_iter().map(|ip| build_multiaddr(ip, port)).collect())
+}
+
+/// Parses `input` as a multiaddr, URL, or `host:port` string and resolves it to one or more concrete,
+/// canonicalized IP multiaddrs.
+///
+/// - `/dns4`, `/dns6` and `/dnsaddr` multiaddrs, and plain `host:port`/URL strings, are resolved via `resolver`.
+/// - Multiaddrs that are already concrete (IP, Tor, I2P, ...) are canonicalized and returned unchanged.
+pub fn resolve_to_multiaddrs(input: &str, resolver: &dyn DnsResolver) -> Result<Vec<Multiaddr>, NetAddressError> {
+    if let Ok(multiaddr) = input.parse::<Multiaddr>() {
+        return match dns_host_position(&multiaddr) {
+            Some((index, host)) => {
+                let ips = resolver.resolve(&host)?;
+                if ips.is_empty() {
+                    return Err(NetAddressError::NoAddressesResolved(host));
+                }
+                Ok(ips.into_iter().map(|ip| replace_dns_component(&multiaddr, index, ip)).collect())
+            },
+            None => Ok(vec![canonicalize_multiaddr(&multiaddr)]),
+        };
+    }
+
+    let (host, port) = parse_host_port_str(input).ok_or_else(|| NetAddressError::InvalidAddress(input.to_string()))?;
+    resolve_host(&host, port, resolver)
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+
+    struct MockResolver(Vec<IpAddr>);
+
+    impl DnsResolver for MockResolver {
+        fn resolve(&self, _host: &str) -> Result<Vec<IpAddr>, NetAddressError> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_ipv4_mapped() {
+        let mapped = IpAddr::V6(Ipv4Addr::new(127, 0, 0, 1).to_ipv6_mapped());
+        assert_eq!(canonicalize_ip(mapped), IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)));
+
+        let genuine_v6 = IpAddr::V6(Ipv6Addr::LOCALHOST);
+        assert_eq!(canonicalize_ip(genuine_v6), genuine_v6);
+    }
+
+    #[test]
+    fn test_parse_host_port_str() {
+        assert_eq!(
+            parse_host_port_str("tcp://example.com:18189"),
+            Some(("example.com".to_string(), Some(18189)))
+        );
+        assert_eq!(
+            parse_host_port_str("example.com:18189"),
+            Some(("example.com".to_string(), Some(18189)))
+        );
+        assert_eq!(parse_host_port_str("example.com"), Some(("example.com".to_string(), None)));
+        assert_eq!(parse_host_port_str(""), None);
+    }
+
+    #[test]
+    fn test_parse_host_port_str_ipv6() {
+        assert_eq!(parse_host_port_str("::1"), Some(("::1".to_string(), None)));
+        assert_eq!(parse_host_port_str("[::1]"), Some(("::1".to_string(), None)));
+        assert_eq!(parse_host_port_str("[::1]:8000"), Some(("::1".to_string(), Some(8000))));
+    }
+
+    #[test]
+    fn test_resolve_to_multiaddrs_dns_preserves_p2p_suffix() {
+        let resolver = MockResolver(vec![IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))]);
+        let resolved = resolve_to_multiaddrs(
+            "/dns4/example.com/tcp/8000/p2p/12D3KooWAicQmo7dSEUjR1j6grtrmKmKz3tbXxAJnwsaSRuXjxMs",
+            &resolver,
+        )
+        .unwrap();
+        assert_eq!(
+            resolved,
+            vec!["/ip4/10.0.0.1/tcp/8000/p2p/12D3KooWAicQmo7dSEUjR1j6grtrmKmKz3tbXxAJnwsaSRuXjxMs"
+                .parse::<Multiaddr>()
+                .unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_resolve_to_multiaddrs_dns_preserves_non_tcp_transport() {
+        let resolver = MockResolver(vec![IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))]);
+        let resolved = resolve_to_multiaddrs("/dns4/example.com/udp/9000/quic-v1", &resolver).unwrap();
+        assert_eq!(
+            resolved,
+            vec!["/ip4/10.0.0.1/udp/9000/quic-v1".parse::<Multiaddr>().unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_resolve_to_multiaddrs_passthrough() {
+        let resolver = MockResolver(vec![]);
+        let addr = "/ip4/10.0.0.1/tcp/8000".parse::<Multiaddr>().unwrap();
+        let resolved = resolve_to_multiaddrs(&addr.to_string(), &resolver).unwrap();
+        assert_eq!(resolved, vec![addr]);
+    }
+
+    #[test]
+    fn test_resolve_to_multiaddrs_dns() {
+        let resolver = MockResolver(vec![IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))]);
+        let resolved = resolve_to_multiaddrs("/dns4/example.com/tcp/8000", &resolver).unwrap();
+        assert_eq!(resolved, vec!["/ip4/10.0.0.1/tcp/8000".parse::<Multiaddr>().unwrap()]);
+    }
+
+    #[test]
+    fn test_resolve_to_multiaddrs_host_port_string() {
+        let resolver = MockResolver(vec![IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2))]);
+        let resolved = resolve_to_multiaddrs("example.com:8000", &resolver).unwrap();
+        assert_eq!(resolved, vec!["/ip4/10.0.0.2/tcp/8000".parse::<Multiaddr>().unwrap()]);
+    }
+
+    #[test]
+    fn test_resolve_to_multiaddrs_no_addresses() {
+        let resolver = MockResolver(vec![]);
+        assert!(resolve_to_multiaddrs("/dns4/example.com/tcp/8000", &resolver).is_err());
+    }
+}
@@ -0,0 +1,25 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+mod dns;
+mod multiaddr_with_stats;
+mod mutliaddresses_with_stats;
+
+pub use dns::{DnsResolver, NetAddressError, SystemDnsResolver};
+pub use multiaddr_with_stats::{to_redacted_string, MultiaddrWithStats, PeerAddrState, PeerAddressSource};
+pub use mutliaddresses_with_stats::MultiaddressesWithStats;
+
+static REDACT_PEER_ADDRESSES: AtomicBool = AtomicBool::new(false);
+
+/// Globally enables or disables redaction of peer addresses in `Display` output across the crate. When enabled,
+/// formatting a [`MultiaddressesWithStats`] with `{}` renders each address with its host redacted (see
+/// [`to_redacted_string`]), so that an accidental `{}` of a peer's address set never leaks its IP to logs.
+pub fn set_redact_peer_addresses(enabled: bool) {
+    REDACT_PEER_ADDRESSES.store(enabled, Ordering::Relaxed);
+}
+
+pub(crate) fn redact_peer_addresses() -> bool {
+    REDACT_PEER_ADDRESSES.load(Ordering::Relaxed)
+}
@@ -0,0 +1,283 @@
+// Copyright 2022 The Tari Project
+// SPDX-License-Identifier: BSD-3-Clause
+
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use multiaddr::{Multiaddr, Protocol};
+use serde::{Deserialize, Serialize};
+
+/// Placeholder substituted for the host component of an address when redacting it for logs.
+const REDACTED_HOST: &str = "REDACTED";
+
+/// Renders `address` with its IP4/IP6/DNS host component replaced by a placeholder, preserving the transport
+/// protocol and port. Tor/I2P addresses are already pseudonymous and are passed through unchanged.
+pub fn to_redacted_string(address: &Multiaddr) -> String {
+    let mut redacted = Multiaddr::empty();
+    for protocol in address.iter() {
+        match protocol {
+            Protocol::Ip4(_) => redacted.push(Protocol::Dns4(REDACTED_HOST.into())),
+            Protocol::Ip6(_) => redacted.push(Protocol::Dns6(REDACTED_HOST.into())),
+            Protocol::Dns(_) => redacted.push(Protocol::Dns(REDACTED_HOST.into())),
+            Protocol::Dns4(_) => redacted.push(Protocol::Dns4(REDACTED_HOST.into())),
+            Protocol::Dns6(_) => redacted.push(Protocol::Dns6(REDACTED_HOST.into())),
+            Protocol::Dnsaddr(_) => redacted.push(Protocol::Dnsaddr(REDACTED_HOST.into())),
+            other => redacted.push(other),
+        }
+    }
+    redacted.to_string()
+}
+
+/// Where a peer address was learned from. Used by [`MultiaddrWithStats::update_source_if_better`] to decide whether
+/// a newly reported source for an already-known address should replace the existing one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub enum PeerAddressSource {
+    /// The address was provided in local configuration
+    Config,
+    /// The address was learned from peer discovery/gossip
+    FromDiscovery,
+    /// The address was provided by the peer itself (e.g. in its `NodeIdentity`)
+    FromNodeIdentity,
+    /// The address was used to successfully establish a connection to the peer
+    FromPeerConnection,
+}
+
+impl PeerAddressSource {
+    /// Returns true if `self` is at least as trustworthy a source as `other`.
+    fn is_at_least_as_good_as(&self, other: &PeerAddressSource) -> bool {
+        self >= other
+    }
+}
+
+/// The state of a single address, derived from the outcome of connection attempts made to it.
+///
+/// Transitions are monotonic-sensitive: an address that has `Responded` can only be demoted to `Failed` by an
+/// actual failed connection attempt, and `AttemptPending` may only be entered from `NeverAttempted` or `Failed`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum PeerAddrState {
+    /// No connection attempt has ever been made to this address
+    #[default]
+    NeverAttempted,
+    /// A connection attempt is currently in flight
+    AttemptPending,
+    /// The most recent connection attempt succeeded
+    Responded,
+    /// The most recent connection attempt failed
+    Failed,
+}
+
+impl PeerAddrState {
+    /// Ranks states from most to least preferable when choosing a "best" address. Lower is better.
+    fn rank(self) -> u8 {
+        match self {
+            PeerAddrState::Responded => 0,
+            PeerAddrState::AttemptPending => 1,
+            PeerAddrState::NeverAttempted => 2,
+            PeerAddrState::Failed => 3,
+        }
+    }
+}
+
+/// This struct tracks usage stats and connection state for a single net address.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MultiaddrWithStats {
+    address: Multiaddr,
+    pub last_seen: Option<NaiveDateTime>,
+    pub last_attempted: Option<NaiveDateTime>,
+    pub connection_attempts: u32,
+    pub avg_latency: Duration,
+    source: PeerAddressSource,
+    state: PeerAddrState,
+}
+
+impl MultiaddrWithStats {
+    pub fn new(address: Multiaddr, source: PeerAddressSource) -> Self {
+        Self {
+            address,
+            last_seen: None,
+            last_attempted: None,
+            connection_attempts: 0,
+            avg_latency: Duration::default(),
+            source,
+            state: PeerAddrState::NeverAttempted,
+        }
+    }
+
+    pub fn address(&self) -> &Multiaddr {
+        &self.address
+    }
+
+    pub fn source(&self) -> &PeerAddressSource {
+        &self.source
+    }
+
+    /// Renders this address with its host component redacted, for use in logs. See [`to_redacted_string`].
+    pub fn redacted(&self) -> String {
+        to_redacted_string(&self.address)
+    }
+
+    pub fn state(&self) -> PeerAddrState {
+        self.state
+    }
+
+    /// The time at which this address was deemed unreachable, if it currently is. `None` if the address has never
+    /// failed a connection attempt, or if it has since responded successfully.
+    pub fn offline_at(&self) -> Option<NaiveDateTime> {
+        match self.state {
+            PeerAddrState::Failed => self.last_attempted,
+            _ => None,
+        }
+    }
+
+    /// Replaces the recorded source with `source` if it is a more trustworthy source than the one currently stored.
+    pub fn update_source_if_better(&mut self, source: &PeerAddressSource) {
+        if source.is_at_least_as_good_as(&self.source) {
+            self.source = *source;
+        }
+    }
+
+    /// Marks that a connection attempt has been started for this address. This is only valid when the address has
+    /// never been attempted or has previously failed; it is a no-op otherwise so that an in-flight dial can never
+    /// clobber a `Responded` address.
+    pub fn mark_attempt_pending(&mut self) {
+        if matches!(self.state, PeerAddrState::NeverAttempted | PeerAddrState::Failed) {
+            self.state = PeerAddrState::AttemptPending;
+        }
+    }
+
+    /// Mark that a successful interaction occurred with this address at `now`. The caller reads the clock once and
+    /// threads it down so that a single `MultiaddressesWithStats` method only ever reads the time once.
+    pub fn mark_last_seen_now(&mut self, now: NaiveDateTime) {
+        self.last_seen = Some(now);
+        self.last_attempted = Some(now);
+        self.state = PeerAddrState::Responded;
+    }
+
+    /// Mark that a connection could not be established with this address at `now`. See [`Self::mark_last_seen_now`]
+    /// for why the caller supplies the current time.
+    pub fn mark_failed_connection_attempt(&mut self, now: NaiveDateTime, _failed_reason: String) {
+        self.last_attempted = Some(now);
+        self.connection_attempts = self.connection_attempts.saturating_add(1);
+        self.state = PeerAddrState::Failed;
+    }
+
+    /// Resets the connection attempts counter. An address that had failed is given a clean slate (`NeverAttempted`)
+    /// so that it is eligible for another round of dialling; an address that has responded keeps that status.
+    pub fn reset_connection_attempts(&mut self) {
+        self.connection_attempts = 0;
+        if self.state == PeerAddrState::Failed {
+            self.state = PeerAddrState::NeverAttempted;
+        }
+    }
+
+    /// Updates the average latency of this address with a new latency sample.
+    pub fn update_latency(&mut self, latency_measurement: Duration) {
+        if self.avg_latency == Duration::default() {
+            self.avg_latency = latency_measurement;
+        } else {
+            self.avg_latency = (self.avg_latency + latency_measurement) / 2;
+        }
+    }
+
+    /// Merges the usage stats of `other` into `self`, keeping the better source, the later `last_seen` and
+    /// `last_attempted`, the fewer `connection_attempts` and the better-ranked state of the two. `avg_latency` is not
+    /// merged; `self` keeps its own.
+    pub fn merge(&mut self, other: &MultiaddrWithStats) {
+        self.update_source_if_better(&other.source);
+        if other.last_seen > self.last_seen {
+            self.last_seen = other.last_seen;
+        }
+        if other.last_attempted > self.last_attempted {
+            self.last_attempted = other.last_attempted;
+        }
+        self.connection_attempts = self.connection_attempts.min(other.connection_attempts);
+        if other.state.rank() < self.state.rank() {
+            self.state = other.state;
+        }
+    }
+
+    /// Sort key used to order addresses from "best" to "worst": by state (`Responded`, then `AttemptPending`, then
+    /// `NeverAttempted`, then `Failed`), then by lowest average latency, then by fewest connection attempts.
+    fn sort_key(&self) -> (u8, Duration, u32) {
+        (self.state.rank(), self.avg_latency, self.connection_attempts)
+    }
+}
+
+impl PartialOrd for MultiaddrWithStats {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MultiaddrWithStats {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn new_address() -> MultiaddrWithStats {
+        MultiaddrWithStats::new(
+            "/ip4/123.0.0.123/tcp/8000".parse().unwrap(),
+            PeerAddressSource::Config,
+        )
+    }
+
+    #[test]
+    fn test_mark_attempt_pending_is_a_no_op_once_responded() {
+        let mut addr = new_address();
+        addr.mark_attempt_pending();
+        assert_eq!(addr.state(), PeerAddrState::AttemptPending);
+
+        addr.mark_last_seen_now(Utc::now().naive_utc());
+        assert_eq!(addr.state(), PeerAddrState::Responded);
+
+        // A new dial attempt must not demote an address that has already responded.
+        addr.mark_attempt_pending();
+        assert_eq!(addr.state(), PeerAddrState::Responded);
+    }
+
+    #[test]
+    fn test_mark_attempt_pending_valid_from_never_attempted_and_failed() {
+        let mut addr = new_address();
+        assert_eq!(addr.state(), PeerAddrState::NeverAttempted);
+        addr.mark_attempt_pending();
+        assert_eq!(addr.state(), PeerAddrState::AttemptPending);
+
+        addr.mark_failed_connection_attempt(Utc::now().naive_utc(), "error".to_string());
+        assert_eq!(addr.state(), PeerAddrState::Failed);
+        addr.mark_attempt_pending();
+        assert_eq!(addr.state(), PeerAddrState::AttemptPending);
+    }
+
+    #[test]
+    fn test_to_redacted_string_ip4() {
+        let addr = "/ip4/123.0.0.123/tcp/8000".parse::<Multiaddr>().unwrap();
+        assert_eq!(to_redacted_string(&addr), "/dns4/REDACTED/tcp/8000");
+    }
+
+    #[test]
+    fn test_to_redacted_string_ip6() {
+        let addr = "/ip6/::1/tcp/8000".parse::<Multiaddr>().unwrap();
+        assert_eq!(to_redacted_string(&addr), "/dns6/REDACTED/tcp/8000");
+    }
+
+    #[test]
+    fn test_to_redacted_string_dns() {
+        let addr = "/dns4/example.com/tcp/8000".parse::<Multiaddr>().unwrap();
+        assert_eq!(to_redacted_string(&addr), "/dns4/REDACTED/tcp/8000");
+    }
+
+    #[test]
+    fn test_to_redacted_string_onion_passed_through_unchanged() {
+        let addr = "/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:1234"
+            .parse::<Multiaddr>()
+            .unwrap();
+        assert_eq!(to_redacted_string(&addr), addr.to_string());
+    }
+}
@@ -11,12 +11,34 @@ use chrono::{NaiveDateTime, Utc};
 use multiaddr::Multiaddr;
 use serde::{Deserialize, Serialize};
 
-use crate::net_address::{multiaddr_with_stats::PeerAddressSource, MultiaddrWithStats};
+use crate::net_address::{
+    dns::resolve_to_multiaddrs,
+    multiaddr_with_stats::{to_redacted_string, PeerAddressSource},
+    redact_peer_addresses,
+    DnsResolver,
+    MultiaddrWithStats,
+    NetAddressError,
+    PeerAddrState,
+    SystemDnsResolver,
+};
+
+/// Converts a `std::time::Duration` to a `chrono::Duration`, saturating at `chrono::Duration::MAX` on overflow.
+fn to_chrono_duration(duration: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(duration).unwrap_or(chrono::Duration::MAX)
+}
 
 /// This struct is used to store a set of different net addresses such as IPv4, IPv6, Tor or I2P for a single peer.
 #[derive(Debug, Clone, Deserialize, Serialize, Default, Eq)]
 pub struct MultiaddressesWithStats {
     addresses: Vec<MultiaddrWithStats>,
+    /// The maximum number of addresses this instance will retain. When `Some`, `housekeep` is called after every
+    /// insertion (`add_address`, `update_addresses`, `merge`) to evict the worst addresses once this bound is exceeded.
+    max_addresses: Option<usize>,
+    /// The base delay of the exponential backoff applied to failed addresses by `next_dialable`. Zero (the
+    /// default) disables backoff, so a failed address is immediately eligible for retry.
+    backoff_base: Duration,
+    /// The maximum delay the exponential backoff in `next_dialable` will ever produce.
+    backoff_cap: Duration,
 }
 
 impl MultiaddressesWithStats {
@@ -26,20 +48,113 @@ impl MultiaddressesWithStats {
     ) -> MultiaddressesWithStats {
         let mut addresses_with_stats = Vec::with_capacity(addresses.len());
         for address in addresses {
-            addresses_with_stats.push(MultiaddrWithStats::new(address, source.clone()));
+            addresses_with_stats.push(MultiaddrWithStats::new(address, *source));
         }
         MultiaddressesWithStats {
             addresses: addresses_with_stats,
+            max_addresses: None,
+            backoff_base: Duration::ZERO,
+            backoff_cap: Duration::ZERO,
         }
     }
 
     pub fn empty() -> Self {
-        MultiaddressesWithStats { addresses: Vec::new() }
+        MultiaddressesWithStats {
+            addresses: Vec::new(),
+            max_addresses: None,
+            backoff_base: Duration::ZERO,
+            backoff_cap: Duration::ZERO,
+        }
     }
 
     /// Constructs a new list of addresses with usage stats from a list of net addresses
-    pub fn new(addresses: Vec<MultiaddrWithStats>) -> MultiaddressesWithStats {
-        MultiaddressesWithStats { addresses }
+    pub fn new(mut addresses: Vec<MultiaddrWithStats>) -> MultiaddressesWithStats {
+        addresses.sort();
+        MultiaddressesWithStats {
+            addresses,
+            max_addresses: None,
+            backoff_base: Duration::ZERO,
+            backoff_cap: Duration::ZERO,
+        }
+    }
+
+    /// Constructs an empty address book that will never hold more than `max` addresses. Once over capacity, the
+    /// methods that insert addresses call `housekeep` automatically to evict the worst entries.
+    pub fn with_capacity(max: usize) -> Self {
+        MultiaddressesWithStats {
+            addresses: Vec::new(),
+            max_addresses: Some(max),
+            backoff_base: Duration::ZERO,
+            backoff_cap: Duration::ZERO,
+        }
+    }
+
+    /// Configures the exponential backoff applied to failed addresses: a failed address becomes dialable again
+    /// `min(base * 2^(connection_attempts - 1), cap)` after its last attempt. See `next_dialable`.
+    pub fn with_backoff(mut self, base: Duration, cap: Duration) -> Self {
+        self.backoff_base = base;
+        self.backoff_cap = cap;
+        self
+    }
+
+    /// Constructs an address set from a list of multiaddrs, URLs or `host:port` strings, resolving any DNS
+    /// (`/dns4`, `/dns6`, `/dnsaddr`) or plain hostnames via the system resolver and canonicalizing the result
+    /// (e.g. collapsing IPv4-mapped IPv6 addresses to IPv4) before they are stored.
+    pub fn from_urls_with_source(inputs: &[&str], source: &PeerAddressSource) -> Result<Self, NetAddressError> {
+        let resolver = SystemDnsResolver;
+        let mut addresses = MultiaddressesWithStats::empty();
+        for input in inputs {
+            for resolved in resolve_to_multiaddrs(input, &resolver)? {
+                addresses.add_address(&resolved, source);
+            }
+        }
+        Ok(addresses)
+    }
+
+    /// Resolves `dns_multiaddr` (or canonicalizes it, if it is already concrete) via `resolver` and adds every
+    /// resulting address to this set, attributing them to `source`.
+    pub fn add_resolved(
+        &mut self,
+        dns_multiaddr: &Multiaddr,
+        resolver: &dyn DnsResolver,
+        source: &PeerAddressSource,
+    ) -> Result<(), NetAddressError> {
+        for resolved in resolve_to_multiaddrs(&dns_multiaddr.to_string(), resolver)? {
+            self.add_address(&resolved, source);
+        }
+        Ok(())
+    }
+
+    /// If this address book is over its configured capacity, evicts the worst entries - ranked by highest
+    /// `connection_attempts`, then oldest `last_seen`, then a non-`None` `offline_at` - until it is within bounds.
+    /// The current `best()` address, if any, is never evicted.
+    pub fn housekeep(&mut self) {
+        let max = match self.max_addresses {
+            Some(max) => max,
+            None => return,
+        };
+        let best_address = self.best().map(|addr| addr.address().clone());
+        while self.addresses.len() > max {
+            let worst_index = self
+                .addresses
+                .iter()
+                .enumerate()
+                .filter(|(_, addr)| Some(addr.address()) != best_address.as_ref())
+                .max_by_key(|(_, addr)| {
+                    (
+                        addr.connection_attempts,
+                        std::cmp::Reverse(addr.last_seen),
+                        addr.offline_at().is_some(),
+                    )
+                })
+                .map(|(i, _)| i);
+            match worst_index {
+                Some(i) => {
+                    self.addresses.remove(i);
+                },
+                None => break,
+            }
+        }
     }
 
     pub fn best(&self) -> Option<&MultiaddrWithStats> {
@@ -107,16 +222,12 @@ impl MultiaddressesWithStats {
     /// Adds a new net address to the peer. This function will not add a duplicate if the address
     /// already exists.
     pub fn add_address(&mut self, net_address: &Multiaddr, source: &PeerAddressSource) {
-        if self.addresses.iter().any(|x| x.address() == net_address) {
-            self.addresses
-                .iter_mut()
-                .find(|x| x.address() == net_address)
-                .unwrap()
-                .update_source_if_better(source);
-        } else {
-            self.addresses
-                .push(MultiaddrWithStats::new(net_address.clone(), source.clone()));
-            self.addresses.sort();
+        match self.find_address_index(net_address) {
+            Some(index) => self.addresses[index].update_source_if_better(source),
+            None => {
+                self.insert_sorted(MultiaddrWithStats::new(net_address.clone(), *source));
+                self.housekeep();
+            },
         }
     }
 
@@ -139,11 +250,10 @@ impl MultiaddressesWithStats {
             .collect::<Vec<_>>();
 
         for address in to_add {
-            self.addresses
-                .push(MultiaddrWithStats::new(address.clone(), source.clone()));
+            self.insert_sorted(MultiaddrWithStats::new(address.clone(), *source));
         }
 
-        self.addresses.sort();
+        self.housekeep();
     }
 
     /// Returns an iterator of addresses ordered from 'best' to 'worst' according to heuristics such as failed
@@ -170,6 +280,8 @@ impl MultiaddressesWithStats {
                 self.addresses.push(addr.clone());
             }
         }
+        self.addresses.sort();
+        self.housekeep();
     }
 
     /// Finds the specified address in the set and allow updating of its variables such as its usage stats
@@ -177,15 +289,35 @@ impl MultiaddressesWithStats {
         self.addresses.iter_mut().find(|a| a.address() == address)
     }
 
+    /// Finds the index of the specified address in the set.
+    fn find_address_index(&self, address: &Multiaddr) -> Option<usize> {
+        self.addresses.iter().position(|a| a.address() == address)
+    }
+
+    /// Inserts `addr` at the position that keeps `self.addresses` sorted best-to-worst, without touching the
+    /// relative order of any other entry.
+    fn insert_sorted(&mut self, addr: MultiaddrWithStats) {
+        let index = self.addresses.partition_point(|a| a <= &addr);
+        self.addresses.insert(index, addr);
+    }
+
+    /// Moves the entry at `index` - whose quality score has just changed - to the position that keeps
+    /// `self.addresses` sorted best-to-worst. This only touches the one entry that changed instead of re-sorting
+    /// (and re-comparing) every address, unlike a full `self.addresses.sort()`.
+    fn reposition(&mut self, index: usize) {
+        let addr = self.addresses.remove(index);
+        self.insert_sorted(addr);
+    }
+
     /// The average connection latency of the provided net address will be updated to include the current measured
     /// latency sample.
     ///
     /// Returns true if the address is contained in this instance, otherwise false
     pub fn update_latency(&mut self, address: &Multiaddr, latency_measurement: Duration) -> bool {
-        match self.find_address_mut(address) {
-            Some(addr) => {
-                addr.update_latency(latency_measurement);
-                self.addresses.sort();
+        match self.find_address_index(address) {
+            Some(index) => {
+                self.addresses[index].update_latency(latency_measurement);
+                self.reposition(index);
                 true
             },
             None => false,
@@ -194,9 +326,9 @@ impl MultiaddressesWithStats {
 
     pub fn update_address_stats<F>(&mut self, address: &Multiaddr, f: F)
     where F: FnOnce(&mut MultiaddrWithStats) {
-        if let Some(addr) = self.find_address_mut(address) {
-            f(addr);
-            self.addresses.sort();
+        if let Some(index) = self.find_address_index(address) {
+            f(&mut self.addresses[index]);
+            self.reposition(index);
         }
     }
 
@@ -204,11 +336,25 @@ impl MultiaddressesWithStats {
     ///
     /// Returns true if the address is contained in this instance, otherwise false
     pub fn mark_last_seen_now(&mut self, address: &Multiaddr) -> bool {
-        match self.find_address_mut(address) {
-            Some(addr) => {
-                addr.mark_last_seen_now();
-                addr.last_attempted = Some(Utc::now().naive_utc());
-                self.addresses.sort();
+        match self.find_address_index(address) {
+            Some(index) => {
+                let now = Utc::now().naive_utc();
+                self.addresses[index].mark_last_seen_now(now);
+                self.reposition(index);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Mark that a connection attempt to the specified address has started
+    ///
+    /// Returns true if the address is contained in this instance, otherwise false
+    pub fn mark_attempt_pending(&mut self, address: &Multiaddr) -> bool {
+        match self.find_address_index(address) {
+            Some(index) => {
+                self.addresses[index].mark_attempt_pending();
+                self.reposition(index);
                 true
             },
             None => false,
@@ -219,11 +365,11 @@ impl MultiaddressesWithStats {
     ///
     /// Returns true if the address is contained in this instance, otherwise false
     pub fn mark_failed_connection_attempt(&mut self, address: &Multiaddr, failed_reason: String) -> bool {
-        match self.find_address_mut(address) {
-            Some(addr) => {
-                addr.mark_failed_connection_attempt(failed_reason);
-                addr.last_attempted = Some(Utc::now().naive_utc());
-                self.addresses.sort();
+        match self.find_address_index(address) {
+            Some(index) => {
+                let now = Utc::now().naive_utc();
+                self.addresses[index].mark_failed_connection_attempt(now, failed_reason);
+                self.reposition(index);
                 true
             },
             None => false,
@@ -234,12 +380,95 @@ impl MultiaddressesWithStats {
     ///
     /// Returns true if the address is contained in this instance, otherwise false
     pub fn reset_connection_attempts(&mut self) {
+        // Every entry's quality score changes here, so a single full sort is cheaper than repositioning each one
+        // individually.
         for a in &mut self.addresses {
             a.reset_connection_attempts();
         }
         self.addresses.sort();
     }
 
+    /// Returns true if any address in this set is `Responded` and was last seen within `liveness_window` of `now`.
+    pub fn is_live(&self, liveness_window: Duration) -> bool {
+        let now = Utc::now().naive_utc();
+        self.live_addresses_at(now, liveness_window).next().is_some()
+    }
+
+    fn live_addresses_at(
+        &self,
+        now: NaiveDateTime,
+        liveness_window: Duration,
+    ) -> impl Iterator<Item = &MultiaddrWithStats> {
+        self.addresses.iter().filter(move |addr| {
+            addr.state() == PeerAddrState::Responded &&
+                addr.last_seen
+                    .is_some_and(|last_seen| now.signed_duration_since(last_seen) <= to_chrono_duration(liveness_window))
+        })
+    }
+
+    /// Returns an iterator over addresses that are currently `Responded` and were last seen within
+    /// `liveness_window` of now, ordered from best to worst.
+    pub fn live_addresses(&self, liveness_window: Duration) -> impl Iterator<Item = &MultiaddrWithStats> {
+        let now = Utc::now().naive_utc();
+        self.live_addresses_at(now, liveness_window)
+    }
+
+    /// Returns an iterator over addresses that are `Failed` or `NeverAttempted` and are eligible to be retried at
+    /// `now` - i.e. they are not still waiting out the exponential backoff configured via `with_backoff` - ordered
+    /// from best to worst. Unlike a plain state filter, this agrees with `next_dialable`/`time_until_next_dialable`
+    /// about which addresses are actually retryable right now.
+    pub fn reconnection_candidates(&self, now: NaiveDateTime) -> impl Iterator<Item = &MultiaddrWithStats> {
+        self.addresses.iter().filter(move |addr| {
+            matches!(addr.state(), PeerAddrState::Failed | PeerAddrState::NeverAttempted) &&
+                match self.next_retry_at(addr) {
+                    Some(next_retry) => next_retry <= now,
+                    None => true,
+                }
+        })
+    }
+
+    /// The time at which `addr` becomes eligible for another connection attempt, or `None` if it already is (it has
+    /// never failed, or no backoff has been configured via `with_backoff`).
+    fn next_retry_at(&self, addr: &MultiaddrWithStats) -> Option<NaiveDateTime> {
+        if addr.state() != PeerAddrState::Failed || self.backoff_cap == Duration::ZERO {
+            return None;
+        }
+        let last_attempted = addr.last_attempted?;
+        let exponent = addr.connection_attempts.saturating_sub(1).min(31);
+        let multiplier = 1u32.checked_shl(exponent).unwrap_or(u32::MAX);
+        let backoff = self.backoff_base.saturating_mul(multiplier).min(self.backoff_cap);
+        Some(last_attempted + to_chrono_duration(backoff))
+    }
+
+    /// Returns the best address that is currently eligible to be dialed: one that has never failed, or whose
+    /// exponential backoff (see `with_backoff`) has elapsed by `now`. Addresses still in backoff are skipped but
+    /// remain in the set.
+    pub fn next_dialable(&self, now: NaiveDateTime) -> Option<&Multiaddr> {
+        self.addresses
+            .iter()
+            .find(|addr| match self.next_retry_at(addr) {
+                Some(next_retry) => next_retry <= now,
+                None => true,
+            })
+            .map(|addr| addr.address())
+    }
+
+    /// Returns how long the caller should sleep before any address in this set becomes dialable, or `None` if one
+    /// already is. Lets a dialer sleep instead of hot-looping on unreachable peers.
+    pub fn time_until_next_dialable(&self, now: NaiveDateTime) -> Option<Duration> {
+        if self.next_dialable(now).is_some() {
+            return None;
+        }
+        self.addresses
+            .iter()
+            .filter_map(|addr| self.next_retry_at(addr))
+            .map(|next_retry| next_retry - now)
+            .filter(|delay| *delay > chrono::Duration::zero())
+            .min()?
+            .to_std()
+            .ok()
+    }
+
     /// Returns the number of addresses
     pub fn len(&self) -> usize {
         self.addresses.len()
@@ -257,6 +486,16 @@ impl MultiaddressesWithStats {
     pub fn addresses(&self) -> &[MultiaddrWithStats] {
         &self.addresses
     }
+
+    /// Renders this address set with each address's host component redacted, safe to log unconditionally. See
+    /// [`crate::net_address::to_redacted_string`].
+    pub fn to_redacted_string(&self) -> String {
+        self.addresses
+            .iter()
+            .map(|a| to_redacted_string(a.address()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
 }
 
 impl PartialEq for MultiaddressesWithStats {
@@ -276,13 +515,22 @@ impl Index<usize> for MultiaddressesWithStats {
 
 impl From<Vec<MultiaddrWithStats>> for MultiaddressesWithStats {
     /// Constructs NetAddressesWithStats from a list of addresses with usage stats
-    fn from(addresses: Vec<MultiaddrWithStats>) -> Self {
-        MultiaddressesWithStats { addresses }
+    fn from(mut addresses: Vec<MultiaddrWithStats>) -> Self {
+        addresses.sort();
+        MultiaddressesWithStats {
+            addresses,
+            max_addresses: None,
+            backoff_base: Duration::ZERO,
+            backoff_cap: Duration::ZERO,
+        }
     }
 }
 
 impl From<MultiaddressesWithStats> for Vec<String> {
     fn from(value: MultiaddressesWithStats) -> Self {
+        if redact_peer_addresses() {
+            return value.addresses.iter().map(|addr| to_redacted_string(addr.address())).collect();
+        }
         value
             .addresses
             .into_iter()
@@ -293,6 +541,9 @@ impl From<MultiaddressesWithStats> for Vec<String> {
 
 impl Display for MultiaddressesWithStats {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if redact_peer_addresses() {
+            return write!(f, "{}", self.to_redacted_string());
+        }
         write!(
             f,
             "{}",
@@ -416,4 +667,161 @@ mod test {
         assert_eq!(net_addresses.addresses[1].connection_attempts, 0);
         assert_eq!(net_addresses.addresses[2].connection_attempts, 0);
     }
+
+    #[test]
+    fn test_next_dialable_backoff() {
+        let net_address1 = "/ip4/123.0.0.123/tcp/8000".parse::<Multiaddr>().unwrap();
+        let net_address2 = "/ip4/125.1.54.254/tcp/7999".parse::<Multiaddr>().unwrap();
+        let mut net_addresses =
+            MultiaddressesWithStats::from_addresses_with_source(vec![net_address1.clone()], &PeerAddressSource::Config)
+                .with_backoff(Duration::from_secs(10), Duration::from_secs(60));
+        net_addresses.add_address(&net_address2, &PeerAddressSource::Config);
+
+        let now = Utc::now().naive_utc();
+        assert_eq!(net_addresses.next_dialable(now), Some(&net_address1));
+        assert!(net_addresses.time_until_next_dialable(now).is_none());
+
+        assert!(net_addresses.mark_failed_connection_attempt(&net_address1, "error".to_string()));
+        // net_address2 has never been attempted, so it remains dialable even though net_address1 is backing off
+        assert_eq!(net_addresses.next_dialable(now), Some(&net_address2));
+
+        assert!(net_addresses.mark_failed_connection_attempt(&net_address2, "error".to_string()));
+        // Both addresses have now failed once and are within their 10s backoff window
+        let now = Utc::now().naive_utc();
+        assert!(net_addresses.next_dialable(now).is_none());
+        let wait = net_addresses.time_until_next_dialable(now).unwrap();
+        assert!(wait <= Duration::from_secs(10));
+
+        let later = now + chrono::Duration::seconds(11);
+        assert!(net_addresses.next_dialable(later).is_some());
+    }
+
+    #[test]
+    fn test_is_live_and_reconnection_candidates() {
+        let net_address1 = "/ip4/123.0.0.123/tcp/8000".parse::<Multiaddr>().unwrap();
+        let net_address2 = "/ip4/125.1.54.254/tcp/7999".parse::<Multiaddr>().unwrap();
+        let net_address3 = "/ip4/175.6.3.145/tcp/8000".parse::<Multiaddr>().unwrap();
+        let mut net_addresses =
+            MultiaddressesWithStats::from_addresses_with_source(vec![net_address1.clone()], &PeerAddressSource::Config);
+        net_addresses.add_address(&net_address2, &PeerAddressSource::Config);
+        net_addresses.add_address(&net_address3, &PeerAddressSource::Config);
+
+        // Nothing has ever responded, so nothing is live and everything is a reconnection candidate.
+        let now = Utc::now().naive_utc();
+        assert!(!net_addresses.is_live(Duration::from_secs(60)));
+        assert_eq!(net_addresses.reconnection_candidates(now).count(), 3);
+
+        assert!(net_addresses.mark_last_seen_now(&net_address1));
+        assert!(net_addresses.mark_failed_connection_attempt(&net_address2, "error".to_string()));
+
+        assert!(net_addresses.is_live(Duration::from_secs(60)));
+        assert_eq!(net_addresses.live_addresses(Duration::from_secs(60)).count(), 1);
+        assert_eq!(
+            net_addresses.live_addresses(Duration::from_secs(60)).next().unwrap().address(),
+            &net_address1
+        );
+
+        // net_address2 failed and net_address3 was never attempted - both are reconnection candidates,
+        // net_address1 (responded) is not.
+        let now = Utc::now().naive_utc();
+        let candidates = net_addresses
+            .reconnection_candidates(now)
+            .map(|a| a.address().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(candidates.len(), 2);
+        assert!(candidates.contains(&net_address2));
+        assert!(candidates.contains(&net_address3));
+
+        // A liveness window of zero excludes even a just-seen address.
+        assert!(!net_addresses.is_live(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_reconnection_candidates_excludes_addresses_still_in_backoff() {
+        let net_address1 = "/ip4/123.0.0.123/tcp/8000".parse::<Multiaddr>().unwrap();
+        let net_address2 = "/ip4/125.1.54.254/tcp/7999".parse::<Multiaddr>().unwrap();
+        let mut net_addresses =
+            MultiaddressesWithStats::from_addresses_with_source(vec![net_address1.clone()], &PeerAddressSource::Config)
+                .with_backoff(Duration::from_secs(10), Duration::from_secs(60));
+        net_addresses.add_address(&net_address2, &PeerAddressSource::Config);
+
+        assert!(net_addresses.mark_failed_connection_attempt(&net_address1, "error".to_string()));
+        assert!(net_addresses.mark_failed_connection_attempt(&net_address2, "error".to_string()));
+
+        // Both addresses have failed once and are within their 10s backoff window, so neither is a reconnection
+        // candidate yet - matching next_dialable's view that nothing is currently dialable.
+        let now = Utc::now().naive_utc();
+        assert!(net_addresses.next_dialable(now).is_none());
+        assert_eq!(net_addresses.reconnection_candidates(now).count(), 0);
+
+        // Once the backoff window has elapsed, both become candidates again.
+        let later = now + chrono::Duration::seconds(11);
+        assert_eq!(net_addresses.reconnection_candidates(later).count(), 2);
+    }
+
+    #[test]
+    fn test_housekeep_evicts_worst_but_keeps_best() {
+        let net_address1 = "/ip4/123.0.0.123/tcp/8000".parse::<Multiaddr>().unwrap();
+        let net_address2 = "/ip4/125.1.54.254/tcp/7999".parse::<Multiaddr>().unwrap();
+        let net_address3 = "/ip4/175.6.3.145/tcp/8000".parse::<Multiaddr>().unwrap();
+        let mut net_addresses = MultiaddressesWithStats::with_capacity(2);
+        net_addresses.add_address(&net_address1, &PeerAddressSource::Config);
+        net_addresses.add_address(&net_address2, &PeerAddressSource::Config);
+
+        // net_address1 becomes the current best by responding, net_address2 racks up failed attempts so it is
+        // the worst entry once a third address pushes the set over capacity.
+        assert!(net_addresses.mark_last_seen_now(&net_address1));
+        assert!(net_addresses.mark_failed_connection_attempt(&net_address2, "error".to_string()));
+        let best_before = net_addresses.best().unwrap().address().clone();
+        assert_eq!(best_before, net_address1);
+
+        // Adding a third address exceeds the capacity of 2, triggering housekeep() to evict the worst entry.
+        net_addresses.add_address(&net_address3, &PeerAddressSource::Config);
+
+        assert_eq!(net_addresses.len(), 2);
+        assert!(net_addresses.contains(&net_address1));
+        assert!(!net_addresses.contains(&net_address2));
+        assert_eq!(net_addresses.best().unwrap().address(), &best_before);
+    }
+
+    struct MockResolver(std::net::IpAddr);
+
+    impl DnsResolver for MockResolver {
+        fn resolve(&self, _host: &str) -> Result<Vec<std::net::IpAddr>, NetAddressError> {
+            Ok(vec![self.0])
+        }
+    }
+
+    #[test]
+    fn test_from_urls_with_source_canonicalizes_and_passes_through() {
+        let net_addresses = MultiaddressesWithStats::from_urls_with_source(
+            &[
+                "/ip4/123.0.0.123/tcp/8000",
+                "/ip4/125.1.54.254/tcp/7999",
+                "/ip6/::ffff:10.0.0.9/tcp/8000",
+            ],
+            &PeerAddressSource::Config,
+        )
+        .unwrap();
+
+        assert_eq!(net_addresses.len(), 3);
+        assert!(net_addresses.contains(&"/ip4/123.0.0.123/tcp/8000".parse::<Multiaddr>().unwrap()));
+        assert!(net_addresses.contains(&"/ip4/125.1.54.254/tcp/7999".parse::<Multiaddr>().unwrap()));
+        // The IPv4-mapped IPv6 input is stored in its IPv4 form
+        assert!(net_addresses.contains(&"/ip4/10.0.0.9/tcp/8000".parse::<Multiaddr>().unwrap()));
+    }
+
+    #[test]
+    fn test_add_resolved_via_mock_dns_resolver() {
+        let resolver = MockResolver("10.0.0.5".parse().unwrap());
+        let mut net_addresses = MultiaddressesWithStats::empty();
+        let dns_multiaddr = "/dns4/example.com/tcp/8000".parse::<Multiaddr>().unwrap();
+
+        net_addresses
+            .add_resolved(&dns_multiaddr, &resolver, &PeerAddressSource::FromDiscovery)
+            .unwrap();
+
+        assert_eq!(net_addresses.len(), 1);
+        assert!(net_addresses.contains(&"/ip4/10.0.0.5/tcp/8000".parse::<Multiaddr>().unwrap()));
+    }
 }